@@ -20,11 +20,13 @@ pub use {
 };
 
 /// Indicates an allocation error.
+///
+/// A zero-size [`Layout`] is not an error: [`Allocator::allocate`] always succeeds for one,
+/// returning a dangling pointer correctly aligned to the requested [`Layout::align`].
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum AllocError {
     OutOfMemory,
     InvalidAlignment,
-    ZeroSize,
 }
 
 use core::{alloc::Layout, ptr::NonNull};
@@ -53,13 +55,64 @@ impl Flags {
     }
 }
 
-[Previous implementations of BitOr, BitAnd, Not remain unchanged...]
+impl core::ops::BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(self, rhs: Flags) -> Flags {
+        Flags(self.0 | rhs.0)
+    }
+}
+
+impl core::ops::BitAnd for Flags {
+    type Output = Flags;
+
+    fn bitand(self, rhs: Flags) -> Flags {
+        Flags(self.0 & rhs.0)
+    }
+}
+
+impl core::ops::Not for Flags {
+    type Output = Flags;
+
+    fn not(self) -> Flags {
+        Flags(!self.0)
+    }
+}
+
+impl core::fmt::Debug for Flags {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Flags({:#x})", self.0)
+    }
+}
 
 /// Allocation flags.
 ///
 /// These are meant to be used in functions that can allocate memory.
 pub mod flags {
-    [Previous flag definitions remain unchanged...]
+    use super::Flags;
+
+    /// Default allocation flags, suitable for almost all in-kernel contexts that are allowed to
+    /// sleep.
+    pub const GFP_KERNEL: Flags = Flags(0);
+
+    /// Atomic allocation context: never sleeps, for use from interrupt context or while holding a
+    /// spinlock.
+    pub const GFP_ATOMIC: Flags = Flags(1 << 0);
+
+    /// Like [`GFP_ATOMIC`], but also permitted to fail cheaply instead of dipping into emergency
+    /// memory reserves.
+    pub const GFP_NOWAIT: Flags = Flags(1 << 1);
+
+    /// Charges the allocation to the current task's memory control group.
+    pub const __GFP_ACCOUNT: Flags = Flags(1 << 2);
+
+    /// Requests that the returned buffer be zeroed.
+    ///
+    /// Unlike the other flags in this module, which are purely a hint to the backing allocator,
+    /// [`Allocator`](super::Allocator) implementations are required to act on this one: the
+    /// returned (or freshly grown) memory is cleared via
+    /// [`Allocator::zero_memory`](super::Allocator::zero_memory) before the call returns.
+    pub const __GFP_ZERO: Flags = Flags(1 << 3);
 }
 
 #[derive(Debug, Clone)]
@@ -70,6 +123,73 @@ pub struct AllocStats {
     pub current_usage: usize,
 }
 
+/// Live atomic accounting backing a single [`Allocator`] implementation's [`AllocStats`].
+///
+/// Only compiled in under `debug_assertions`, mirroring [`debug_allocation`], so that the
+/// accounting adds no overhead to release builds.
+#[cfg(debug_assertions)]
+pub(crate) struct AllocStatsCounters {
+    total_allocated: core::sync::atomic::AtomicUsize,
+    total_freed: core::sync::atomic::AtomicUsize,
+    current_usage: core::sync::atomic::AtomicUsize,
+    peak_usage: core::sync::atomic::AtomicUsize,
+}
+
+#[cfg(debug_assertions)]
+impl AllocStatsCounters {
+    pub(crate) const fn new() -> Self {
+        use core::sync::atomic::AtomicUsize;
+
+        Self {
+            total_allocated: AtomicUsize::new(0),
+            total_freed: AtomicUsize::new(0),
+            current_usage: AtomicUsize::new(0),
+            peak_usage: AtomicUsize::new(0),
+        }
+    }
+
+    /// Records a successful (re)allocation that changed the tracked usage from `old_size` to
+    /// `new_size` bytes.
+    ///
+    /// All updates use relaxed ordering: these counters are purely informational and are not used
+    /// to synchronize access to the underlying memory.
+    pub(crate) fn record(&self, old_size: usize, new_size: usize) {
+        use core::sync::atomic::Ordering::Relaxed;
+
+        if new_size > old_size {
+            self.total_allocated.fetch_add(new_size - old_size, Relaxed);
+            self.current_usage.fetch_add(new_size - old_size, Relaxed);
+        } else if old_size > new_size {
+            self.total_freed.fetch_add(old_size - new_size, Relaxed);
+            self.current_usage.fetch_sub(old_size - new_size, Relaxed);
+        }
+
+        let current = self.current_usage.load(Relaxed);
+        let mut peak = self.peak_usage.load(Relaxed);
+        while current > peak {
+            match self
+                .peak_usage
+                .compare_exchange_weak(peak, current, Relaxed, Relaxed)
+            {
+                Ok(_) => break,
+                Err(p) => peak = p,
+            }
+        }
+    }
+
+    /// Snapshots the current counters into an [`AllocStats`].
+    pub(crate) fn snapshot(&self) -> AllocStats {
+        use core::sync::atomic::Ordering::Relaxed;
+
+        AllocStats {
+            total_allocated: self.total_allocated.load(Relaxed),
+            total_freed: self.total_freed.load(Relaxed),
+            current_usage: self.current_usage.load(Relaxed),
+            peak_usage: self.peak_usage.load(Relaxed),
+        }
+    }
+}
+
 /// The kernel's [`Allocator`] trait.
 ///
 /// An implementation of [`Allocator`] can allocate, re-allocate and free memory buffers described
@@ -78,12 +198,63 @@ pub struct AllocStats {
 /// [`Allocator`] is designed to be implemented as a ZST; [`Allocator`] functions do not operate on
 /// an object instance.
 ///
-/// [Rest of the original documentation...]
+/// # Safety
+///
+/// Implementers must ensure that `realloc` never returns a dangling, misaligned or otherwise
+/// invalid pointer for a non-zero-size `new_layout`, and that a pointer returned for `new_layout`
+/// remains valid for reads and writes of `new_layout.size()` bytes, correctly aligned to
+/// `new_layout.align()`, until it is passed back to `realloc` or `deallocate` on the same
+/// allocator.
 pub unsafe trait Allocator {
     const DEFAULT_CAPACITY: usize = 4096;
 
+    /// A zero-size `layout` is always allocatable: [`Allocator::allocate`] serves it with a
+    /// dangling, correctly aligned pointer without touching the backing primitive.
     fn can_allocate(&self, layout: Layout) -> bool {
-        layout.size() > 0 && layout.align().is_power_of_two()
+        layout.align().is_power_of_two()
+    }
+
+    /// Allocate memory based on `layout` and `flags`.
+    ///
+    /// On success, returns a buffer represented as [`NonNull<u8>`] that satisfies the size and
+    /// alignment constraints of `layout`. For a zero-size `layout`, this always succeeds with a
+    /// dangling pointer correctly aligned to `layout.align()`; the pointer must never be
+    /// dereferenced.
+    fn allocate(&self, layout: Layout, flags: Flags) -> Result<NonNull<u8>, AllocError> {
+        // SAFETY: `None` together with the zero-sized `Layout::new::<()>()` tells `realloc` that
+        // there is no existing allocation, which is always a valid combination to pass in.
+        unsafe { self.realloc(None, layout, Layout::new::<()>(), flags) }
+    }
+
+    /// Re-allocate an existing allocation to satisfy `new_layout`, or create a new one.
+    ///
+    /// # Safety
+    ///
+    /// - If `ptr` is `Some`, it must point to a valid allocation previously returned by this
+    ///   allocator's [`Allocator::allocate`] or [`Allocator::realloc`], allocated with exactly
+    ///   `old_layout`.
+    /// - If `ptr` is `None`, `old_layout.size()` must be zero.
+    unsafe fn realloc(
+        &self,
+        ptr: Option<NonNull<u8>>,
+        new_layout: Layout,
+        old_layout: Layout,
+        flags: Flags,
+    ) -> Result<NonNull<u8>, AllocError>;
+
+    /// Free an existing allocation described by `layout`.
+    ///
+    /// A no-op if `layout.size() == 0`: such an allocation was never passed to the backing
+    /// primitive in the first place, so `ptr` is a dangling pointer rather than a real one.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a valid allocation previously returned by [`Allocator::allocate`] or
+    /// [`Allocator::realloc`] on `self`, allocated with exactly `layout`.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // SAFETY: `ptr` and `layout` satisfy `realloc`'s safety requirements for an existing
+        // allocation, and a zero-sized `new_layout` tells `realloc` to free rather than resize.
+        let _ = unsafe { self.realloc(Some(ptr), Layout::new::<()>(), layout, flags::GFP_KERNEL) };
     }
 
     fn get_stats(&self) -> AllocStats {
@@ -96,10 +267,9 @@ pub unsafe trait Allocator {
     }
 
     unsafe fn zero_memory(ptr: NonNull<u8>, size: usize) {
-        ptr.as_ptr().write_bytes(0, size);
+        // SAFETY: the caller guarantees that `ptr` is valid for writes of `size` bytes.
+        unsafe { ptr.as_ptr().write_bytes(0, size) };
     }
-
-    [Previous Allocator trait methods with original documentation remain unchanged...]
 }
 
 #[cfg(debug_assertions)]
@@ -114,5 +284,88 @@ pub(crate) fn debug_allocation(layout: &Layout, flags: Flags) {
 
 /// Returns a properly aligned dangling pointer from the given `layout`.
 pub(crate) fn dangling_from_layout(layout: Layout) -> NonNull<u8> {
-    [Previous implementation remains unchanged...]
+    let align = layout.align();
+
+    // SAFETY: `Layout` guarantees that `align` is non-zero.
+    unsafe { NonNull::new_unchecked(align as *mut u8) }
+}
+
+/// The minimum alignment the slab allocator guarantees for any allocation, regardless of the
+/// requested size; mirrors the architecture's `ARCH_KMALLOC_MINALIGN`.
+pub(crate) const SLAB_MINALIGN: usize = 8;
+
+/// Computes the size a slab-backed [`Allocator`] should actually request for `new_layout`.
+///
+/// The slab allocator only guarantees that *power-of-two* sized blocks are naturally aligned to
+/// their size. So for a requested alignment above [`SLAB_MINALIGN`], padding `new_layout`'s size
+/// up to a multiple of its alignment and then rounding that up to the next power of two yields a
+/// size the slab allocator will place on a boundary satisfying the alignment. Below
+/// [`SLAB_MINALIGN`], the slab allocator's own minimum alignment already covers the request, and
+/// no rounding is needed.
+pub(crate) fn aligned_size(new_layout: Layout) -> usize {
+    let layout = new_layout.pad_to_align();
+
+    if layout.align() > SLAB_MINALIGN {
+        layout.size().next_power_of_two()
+    } else {
+        layout.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligned_size_at_slab_minalign() {
+        let layout = Layout::from_size_align(42, SLAB_MINALIGN).unwrap();
+
+        // At `SLAB_MINALIGN`, only `pad_to_align` applies; no power-of-two rounding.
+        assert_eq!(aligned_size(layout), layout.pad_to_align().size());
+    }
+
+    #[test]
+    fn aligned_size_above_slab_minalign_rounds_to_power_of_two() {
+        let align = SLAB_MINALIGN * 4;
+        let layout = Layout::from_size_align(align + 1, align).unwrap();
+
+        assert_eq!(
+            aligned_size(layout),
+            layout.pad_to_align().size().next_power_of_two()
+        );
+    }
+
+    #[test]
+    fn aligned_size_shrink() {
+        let grown = Layout::from_size_align(SLAB_MINALIGN * 8, SLAB_MINALIGN * 2).unwrap();
+        let shrunk = Layout::from_size_align(SLAB_MINALIGN, SLAB_MINALIGN * 2).unwrap();
+
+        assert!(aligned_size(shrunk) < aligned_size(grown));
+        assert_eq!(
+            aligned_size(shrunk),
+            shrunk.pad_to_align().size().next_power_of_two()
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn alloc_stats_track_peak_and_current_usage() {
+        let stats = AllocStatsCounters::new();
+
+        stats.record(0, 16); // allocate 16 bytes
+        stats.record(16, 64); // grow to 64 bytes
+        stats.record(64, 32); // shrink to 32 bytes
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.total_allocated, 16 + 48);
+        assert_eq!(snapshot.total_freed, 32);
+        assert_eq!(snapshot.current_usage, 32);
+        assert_eq!(snapshot.peak_usage, 64);
+
+        stats.record(32, 0); // free
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.current_usage, 0);
+        assert_eq!(snapshot.peak_usage, 64);
+    }
 }
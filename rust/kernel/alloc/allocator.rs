@@ -0,0 +1,363 @@
+// SPDX-License-Identifier: GPL-2.0
+
+//! Concrete [`Allocator`] implementations backing [`KBox`](super::kbox::KBox),
+//! [`VBox`](super::kbox::VBox), [`KVBox`](super::kbox::KVBox) and their `Vec` counterparts.
+
+#[cfg(debug_assertions)]
+use super::{AllocStats, AllocStatsCounters};
+use super::{aligned_size, dangling_from_layout, flags, AllocError, Allocator, Flags};
+use crate::bindings;
+use core::{alloc::Layout, ptr, ptr::NonNull};
+
+/// The kernel's default allocator, backed by the slab allocator (`krealloc`/`kfree`).
+///
+/// Allocations are physically contiguous, which makes [`Kmalloc`] suitable for DMA buffers, but
+/// limits the maximum allocation size to what the slab allocator can serve as a single
+/// contiguous block.
+#[derive(Default, Clone, Copy)]
+pub struct Kmalloc;
+
+/// An allocator backed by `vmalloc`'s virtually contiguous address space (`vrealloc`/`vfree`).
+///
+/// Allocations are only virtually contiguous, which lets [`Vmalloc`] serve buffers larger than
+/// the slab allocator can, at the cost of page-table setup overhead and non-DMA-able memory.
+#[derive(Default, Clone, Copy)]
+pub struct Vmalloc;
+
+/// An allocator that serves small requests through the physically contiguous [`Kmalloc`] path
+/// and larger ones through [`Vmalloc`].
+///
+/// At or below [`KVMALLOC_THRESHOLD`], [`KVmalloc`] behaves exactly like [`Kmalloc`], including
+/// failing with [`AllocError::OutOfMemory`] when the slab allocator does. Above it, requests are
+/// served by [`Vmalloc`] directly; the slab path is not tried first. Callers that always require
+/// physically contiguous memory should use [`Kmalloc`] directly instead.
+#[derive(Default, Clone, Copy)]
+pub struct KVmalloc;
+
+/// Requests at or below this size are always served by the slab ([`Kmalloc`]) path; larger
+/// requests are always served by [`Vmalloc`] in [`KVmalloc`].
+const KVMALLOC_THRESHOLD: usize = bindings::PAGE_SIZE as usize;
+
+#[cfg(debug_assertions)]
+static KMALLOC_STATS: AllocStatsCounters = AllocStatsCounters::new();
+
+#[cfg(debug_assertions)]
+static VMALLOC_STATS: AllocStatsCounters = AllocStatsCounters::new();
+
+#[cfg(debug_assertions)]
+static KVMALLOC_STATS: AllocStatsCounters = AllocStatsCounters::new();
+
+/// Frees a previously `krealloc`-allocated pointer, if any.
+///
+/// # Safety
+///
+/// `ptr`, if `Some`, must point to a valid allocation previously returned by [`Kmalloc`].
+unsafe fn kmalloc_free(ptr: Option<NonNull<u8>>) {
+    if let Some(ptr) = ptr {
+        // SAFETY: by the caller's contract, `ptr` was allocated by `krealloc`/`kmalloc`.
+        unsafe { bindings::kfree(ptr.as_ptr().cast()) };
+    }
+}
+
+/// Frees a previously `vrealloc`-allocated pointer, if any.
+///
+/// # Safety
+///
+/// `ptr`, if `Some`, must point to a valid allocation previously returned by [`Vmalloc`].
+unsafe fn vmalloc_free(ptr: Option<NonNull<u8>>) {
+    if let Some(ptr) = ptr {
+        // SAFETY: by the caller's contract, `ptr` was allocated by `vmalloc`/`vrealloc`.
+        unsafe { bindings::vfree(ptr.as_ptr().cast()) };
+    }
+}
+
+// SAFETY: `Kmalloc` never hands out a pointer that isn't either a dangling pointer aligned to the
+// requested `Layout` (for a zero-size request) or obtained from `krealloc`, matching the trait's
+// contract.
+unsafe impl Allocator for Kmalloc {
+    unsafe fn realloc(
+        &self,
+        ptr: Option<NonNull<u8>>,
+        new_layout: Layout,
+        old_layout: Layout,
+        flags: Flags,
+    ) -> Result<NonNull<u8>, AllocError> {
+        // A zero-size `old_layout` means `ptr` (if any) is a dangling pointer from a previous
+        // zero-size allocation, never actually handed to `krealloc`: treat it as absent.
+        let ptr = ptr.filter(|_| old_layout.size() > 0);
+
+        if new_layout.size() == 0 {
+            // SAFETY: `ptr` was allocated by this allocator per the caller's contract.
+            unsafe { kmalloc_free(ptr) };
+
+            #[cfg(debug_assertions)]
+            KMALLOC_STATS.record(ptr.map_or(0, |_| old_layout.size()), 0);
+
+            return Ok(dangling_from_layout(new_layout));
+        }
+
+        let src = ptr.map_or(ptr::null_mut(), NonNull::as_ptr);
+        let size = aligned_size(new_layout);
+        // The context flags (atomic, no-wait, accounted, ...) are meaningful to `krealloc`
+        // itself; the zeroing flag is handled explicitly below instead, so it is stripped here
+        // rather than relied upon across the FFI boundary.
+        let raw_flags = (flags & !flags::__GFP_ZERO).as_raw();
+
+        // SAFETY: `src` is either NULL or a pointer previously returned by `krealloc`, as
+        // `krealloc`'s own safety contract requires. `size` is `aligned_size(new_layout)`, which
+        // is always >= `new_layout.size()` and, for `new_layout.align() > SLAB_MINALIGN`, a power
+        // of two that the slab allocator naturally aligns to at least `new_layout.align()`.
+        let raw_ptr = unsafe { bindings::krealloc(src.cast(), size, raw_flags) } as *mut u8;
+        let new_ptr = NonNull::new(raw_ptr).ok_or(AllocError::OutOfMemory)?;
+
+        if flags.contains(flags::__GFP_ZERO) {
+            // Clamped to `new_layout.size()`: a shrinking `realloc` can have `old_layout.size()`
+            // larger than `new_layout.size()`, and `new_ptr` is only valid for the latter.
+            let old_size = core::cmp::min(ptr.map_or(0, |_| old_layout.size()), new_layout.size());
+
+            // SAFETY: `new_ptr` is valid for writes of `new_layout.size()` bytes, and `old_size`
+            // is at most that, leaving a valid offset and length for the freshly-grown tail.
+            unsafe {
+                Self::zero_memory(
+                    NonNull::new_unchecked(new_ptr.as_ptr().add(old_size)),
+                    new_layout.size().saturating_sub(old_size),
+                )
+            };
+        }
+
+        #[cfg(debug_assertions)]
+        KMALLOC_STATS.record(ptr.map_or(0, |_| old_layout.size()), new_layout.size());
+
+        Ok(new_ptr)
+    }
+
+    #[cfg(debug_assertions)]
+    fn get_stats(&self) -> AllocStats {
+        KMALLOC_STATS.snapshot()
+    }
+}
+
+// SAFETY: `Vmalloc` never hands out a pointer that isn't either a dangling pointer aligned to the
+// requested `Layout` (for a zero-size request) or obtained from `vrealloc`, matching the trait's
+// contract.
+unsafe impl Allocator for Vmalloc {
+    unsafe fn realloc(
+        &self,
+        ptr: Option<NonNull<u8>>,
+        new_layout: Layout,
+        old_layout: Layout,
+        flags: Flags,
+    ) -> Result<NonNull<u8>, AllocError> {
+        // A zero-size `old_layout` means `ptr` (if any) is a dangling pointer from a previous
+        // zero-size allocation, never actually handed to `vrealloc`: treat it as absent.
+        let ptr = ptr.filter(|_| old_layout.size() > 0);
+
+        if new_layout.size() == 0 {
+            // SAFETY: `ptr` was allocated by this allocator per the caller's contract.
+            unsafe { vmalloc_free(ptr) };
+
+            #[cfg(debug_assertions)]
+            VMALLOC_STATS.record(ptr.map_or(0, |_| old_layout.size()), 0);
+
+            return Ok(dangling_from_layout(new_layout));
+        }
+
+        // `vmalloc` only guarantees page alignment.
+        if new_layout.align() > bindings::PAGE_SIZE as usize {
+            return Err(AllocError::InvalidAlignment);
+        }
+
+        let src = ptr.map_or(ptr::null_mut(), NonNull::as_ptr);
+        // See `Kmalloc::realloc`: the zeroing flag is handled explicitly below instead of being
+        // passed across the FFI boundary.
+        let raw_flags = (flags & !flags::__GFP_ZERO).as_raw();
+
+        // SAFETY: `src` is either NULL or a pointer previously returned by `vrealloc`, as
+        // `vrealloc`'s own safety contract requires. `vmalloc` allocations are always page
+        // aligned, which satisfies `new_layout.align()`, checked above to be at most `PAGE_SIZE`.
+        let raw_ptr =
+            unsafe { bindings::vrealloc(src.cast(), new_layout.size(), raw_flags) } as *mut u8;
+        let new_ptr = NonNull::new(raw_ptr).ok_or(AllocError::OutOfMemory)?;
+
+        if flags.contains(flags::__GFP_ZERO) {
+            // Clamped to `new_layout.size()`: a shrinking `realloc` can have `old_layout.size()`
+            // larger than `new_layout.size()`, and `new_ptr` is only valid for the latter.
+            let old_size = core::cmp::min(ptr.map_or(0, |_| old_layout.size()), new_layout.size());
+
+            // SAFETY: `new_ptr` is valid for writes of `new_layout.size()` bytes, and `old_size`
+            // is at most that, leaving a valid offset and length for the freshly-grown tail.
+            unsafe {
+                Self::zero_memory(
+                    NonNull::new_unchecked(new_ptr.as_ptr().add(old_size)),
+                    new_layout.size().saturating_sub(old_size),
+                )
+            };
+        }
+
+        #[cfg(debug_assertions)]
+        VMALLOC_STATS.record(ptr.map_or(0, |_| old_layout.size()), new_layout.size());
+
+        Ok(new_ptr)
+    }
+
+    #[cfg(debug_assertions)]
+    fn get_stats(&self) -> AllocStats {
+        VMALLOC_STATS.snapshot()
+    }
+}
+
+// SAFETY: `KVmalloc` only ever hands out pointers obtained from `Kmalloc` or `Vmalloc`, and frees
+// them through the matching backend based on the same size threshold used to allocate them,
+// matching the trait's contract.
+unsafe impl Allocator for KVmalloc {
+    unsafe fn realloc(
+        &self,
+        ptr: Option<NonNull<u8>>,
+        new_layout: Layout,
+        old_layout: Layout,
+        flags: Flags,
+    ) -> Result<NonNull<u8>, AllocError> {
+        // A zero-size `old_layout` means `ptr` (if any) is a dangling pointer from a previous
+        // zero-size allocation, never actually handed to either backend: treat it as absent.
+        let ptr = ptr.filter(|_| old_layout.size() > 0);
+
+        // Whether the existing allocation (if any) was served by the slab path. This is what
+        // `ptr` was actually allocated through, and must drive the backend we hand `ptr` to
+        // below — `new_layout.size()` alone says nothing about where `ptr` came from.
+        let old_in_slab = ptr.is_none() || old_layout.size() <= KVMALLOC_THRESHOLD;
+
+        // Below the threshold, behave exactly like `Kmalloc`, but only when the existing
+        // allocation (if any) was itself served by the slab path: otherwise `ptr` was obtained
+        // from `vmalloc`/`vrealloc`, and handing it to `krealloc` would be unsound.
+        if new_layout.size() <= KVMALLOC_THRESHOLD && old_in_slab {
+            // SAFETY: `ptr`/`old_layout` satisfy `Kmalloc::realloc`'s requirements because any
+            // existing allocation was itself served by the slab path.
+            let result = unsafe { Kmalloc.realloc(ptr, new_layout, old_layout, flags) };
+
+            #[cfg(debug_assertions)]
+            if result.is_ok() {
+                KVMALLOC_STATS.record(ptr.map_or(0, |_| old_layout.size()), new_layout.size());
+            }
+
+            return result;
+        }
+
+        // The existing allocation is already on the `Vmalloc` path (`old_in_slab` is only false
+        // when `ptr` is `Some`).
+        if let (false, Some(old_ptr)) = (old_in_slab, ptr) {
+            // Freeing, or still above the threshold: let `vrealloc` free or resize it in place
+            // rather than allocating fresh and copying.
+            if new_layout.size() == 0 || new_layout.size() > KVMALLOC_THRESHOLD {
+                // SAFETY: `ptr`/`old_layout` satisfy `Vmalloc::realloc`'s requirements because
+                // the existing allocation was itself served by the `vmalloc` path.
+                let result = unsafe { Vmalloc.realloc(ptr, new_layout, old_layout, flags) };
+
+                #[cfg(debug_assertions)]
+                if result.is_ok() {
+                    KVMALLOC_STATS.record(old_layout.size(), new_layout.size());
+                }
+
+                return result;
+            }
+
+            // Shrinking to or below the threshold: move the allocation to the slab path, so that
+            // a later `realloc`/`deallocate` with `new_layout` reaches the backend that owns it.
+            let new_ptr = Kmalloc.allocate(new_layout, flags)?;
+
+            // SAFETY: `old_ptr` is valid for reads of `old_layout.size()` bytes, which is larger
+            // than `new_layout.size()` here, and `new_ptr` is a fresh allocation valid for writes
+            // of `new_layout.size()` bytes; the two cannot overlap. `old_ptr` was served by the
+            // `vmalloc` path and is not used afterwards.
+            unsafe {
+                core::ptr::copy_nonoverlapping(old_ptr.as_ptr(), new_ptr.as_ptr(), new_layout.size());
+                vmalloc_free(ptr);
+            }
+
+            #[cfg(debug_assertions)]
+            KVMALLOC_STATS.record(old_layout.size(), new_layout.size());
+
+            return Ok(new_ptr);
+        }
+
+        // The request is above the threshold and the existing allocation, if any, was served by
+        // the slab path: serve (or move) the allocation through `Vmalloc`.
+        let new_ptr = Vmalloc.allocate(new_layout, flags)?;
+
+        if let Some(old_ptr) = ptr {
+            let copy_size = core::cmp::min(new_layout.size(), old_layout.size());
+
+            // SAFETY: `old_ptr` is valid for reads of `copy_size` bytes (at most
+            // `old_layout.size()`, by the caller's contract) and `new_ptr` is a fresh allocation
+            // valid for writes of at least `copy_size` bytes; the two cannot overlap.
+            unsafe {
+                core::ptr::copy_nonoverlapping(old_ptr.as_ptr(), new_ptr.as_ptr(), copy_size)
+            };
+
+            // `old_in_slab` is `true` here, since the `!old_in_slab` case already returned above:
+            // the old allocation was served by the slab path.
+            // SAFETY: see above.
+            unsafe { kmalloc_free(Some(old_ptr)) };
+        }
+
+        #[cfg(debug_assertions)]
+        KVMALLOC_STATS.record(ptr.map_or(0, |_| old_layout.size()), new_layout.size());
+
+        Ok(new_ptr)
+    }
+
+    #[cfg(debug_assertions)]
+    fn get_stats(&self) -> AllocStats {
+        KVMALLOC_STATS.snapshot()
+    }
+}
+
+/// A ZST bridge that lets `core`/`alloc` collections (`Box`, `Vec`, `String`, ...) allocate
+/// through [`Kmalloc`], without relying on the unstable `allocator_api` feature.
+///
+/// Registered below as the crate's [`global_allocator`](macro@core::prelude::v1::global_allocator),
+/// this unifies every default allocation onto the same flag handling and [`AllocStats`] as
+/// [`KBox`](super::kbox::KBox) and [`KVec`](super::kvec::KVec).
+struct KernelAllocator;
+
+// SAFETY: `Kmalloc::realloc` never returns a pointer that doesn't satisfy the requested `Layout`,
+// and `KernelAllocator` never frees a pointer it didn't itself hand out with the same `Layout`.
+unsafe impl core::alloc::GlobalAlloc for KernelAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: `layout` is a valid, non-zero-size request; `GlobalAlloc`'s contract forbids
+        // zero-size layouts.
+        match unsafe { Kmalloc.allocate(layout, flags::GFP_KERNEL) } {
+            Ok(ptr) => ptr.as_ptr(),
+            Err(_) => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: the caller guarantees `ptr` was returned by a prior call to this allocator's
+        // `alloc`/`alloc_zeroed`/`realloc` with the same `layout`.
+        unsafe { Kmalloc.deallocate(NonNull::new_unchecked(ptr), layout) };
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: see `alloc`.
+        match unsafe { Kmalloc.allocate(layout, flags::__GFP_ZERO) } {
+            Ok(ptr) => ptr.as_ptr(),
+            Err(_) => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let Ok(new_layout) = Layout::from_size_align(new_size, layout.align()) else {
+            return ptr::null_mut();
+        };
+
+        // SAFETY: `ptr` was returned by a prior call to this allocator with `layout`, as
+        // `GlobalAlloc::realloc`'s contract requires.
+        match unsafe { Kmalloc.realloc(NonNull::new(ptr), new_layout, layout, flags::GFP_KERNEL) } {
+            Ok(ptr) => ptr.as_ptr(),
+            Err(_) => ptr::null_mut(),
+        }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: KernelAllocator = KernelAllocator;
@@ -0,0 +1,425 @@
+// SPDX-License-Identifier: GPL-2.0
+
+//! A userspace-backed stand-in for [`super::allocator`], used in `test`/`testlib` builds where
+//! the real slab/vmalloc primitives are unavailable.
+//!
+//! This module intentionally mirrors the public surface of [`super::allocator`] (`Kmalloc`,
+//! `Vmalloc`, `KVmalloc`) so that generic code such as [`super::kbox`] and [`super::kvec`] can be
+//! exercised without depending on running inside the kernel.
+
+#[cfg(debug_assertions)]
+use super::{AllocStats, AllocStatsCounters};
+use super::{aligned_size, dangling_from_layout, flags, AllocError, Allocator, Flags};
+use core::{alloc::Layout, ffi::c_void, ptr, ptr::NonNull};
+
+extern "C" {
+    fn realloc(ptr: *mut c_void, size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+}
+
+/// Stand-in for [`super::allocator::Kmalloc`], backed by the host's `realloc`/`free`.
+#[derive(Default, Clone, Copy)]
+pub struct Kmalloc;
+
+/// Stand-in for [`super::allocator::Vmalloc`], backed by the host's `realloc`/`free`.
+#[derive(Default, Clone, Copy)]
+pub struct Vmalloc;
+
+/// Stand-in for [`super::allocator::KVmalloc`]; mirrors its threshold/backend-switch logic over
+/// [`Kmalloc`] and [`Vmalloc`], even though both are themselves backed by the host's
+/// `realloc`/`free` here.
+#[derive(Default, Clone, Copy)]
+pub struct KVmalloc;
+
+#[cfg(debug_assertions)]
+static KMALLOC_STATS: AllocStatsCounters = AllocStatsCounters::new();
+
+/// Scaled-down mirror of `allocator::KVMALLOC_THRESHOLD`: small enough that tests can cross it
+/// with ordinary-sized buffers instead of needing real page-sized ones.
+const KVMALLOC_THRESHOLD: usize = 64;
+
+// SAFETY: see `allocator::Kmalloc`'s impl; the same contract holds for the host `realloc`/`free`.
+unsafe impl Allocator for Kmalloc {
+    unsafe fn realloc(
+        &self,
+        ptr: Option<NonNull<u8>>,
+        new_layout: Layout,
+        old_layout: Layout,
+        flags: Flags,
+    ) -> Result<NonNull<u8>, AllocError> {
+        // A zero-size `old_layout` means `ptr` (if any) is a dangling pointer from a previous
+        // zero-size allocation, never actually handed to `realloc`: treat it as absent.
+        let ptr = ptr.filter(|_| old_layout.size() > 0);
+
+        if new_layout.size() == 0 {
+            if let Some(ptr) = ptr {
+                // SAFETY: `ptr` was allocated by this allocator per the caller's contract.
+                unsafe { free(ptr.as_ptr().cast()) };
+            }
+
+            #[cfg(debug_assertions)]
+            KMALLOC_STATS.record(ptr.map_or(0, |_| old_layout.size()), 0);
+
+            return Ok(dangling_from_layout(new_layout));
+        }
+
+        let src = ptr.map_or(ptr::null_mut(), NonNull::as_ptr);
+        let size = aligned_size(new_layout);
+
+        // SAFETY: `src` is either NULL or a pointer previously returned by `realloc`, as
+        // `realloc`'s own contract requires.
+        let raw_ptr = unsafe { realloc(src.cast(), size) } as *mut u8;
+        let new_ptr = NonNull::new(raw_ptr).ok_or(AllocError::OutOfMemory)?;
+
+        if flags.contains(flags::__GFP_ZERO) {
+            // Clamped to `new_layout.size()`: a shrinking `realloc` can have `old_layout.size()`
+            // larger than `new_layout.size()`, and `new_ptr` is only valid for the latter.
+            let old_size = core::cmp::min(ptr.map_or(0, |_| old_layout.size()), new_layout.size());
+
+            // SAFETY: `new_ptr` is valid for writes of `new_layout.size()` bytes, and `old_size`
+            // is at most that, leaving a valid offset and length for the freshly-grown tail.
+            unsafe {
+                Self::zero_memory(
+                    NonNull::new_unchecked(new_ptr.as_ptr().add(old_size)),
+                    new_layout.size().saturating_sub(old_size),
+                )
+            };
+        }
+
+        #[cfg(debug_assertions)]
+        KMALLOC_STATS.record(ptr.map_or(0, |_| old_layout.size()), new_layout.size());
+
+        Ok(new_ptr)
+    }
+
+    #[cfg(debug_assertions)]
+    fn get_stats(&self) -> AllocStats {
+        KMALLOC_STATS.snapshot()
+    }
+}
+
+// SAFETY: see above.
+unsafe impl Allocator for Vmalloc {
+    unsafe fn realloc(
+        &self,
+        ptr: Option<NonNull<u8>>,
+        new_layout: Layout,
+        old_layout: Layout,
+        flags: Flags,
+    ) -> Result<NonNull<u8>, AllocError> {
+        // SAFETY: forwarding preserves `realloc`'s safety requirements.
+        unsafe { Kmalloc.realloc(ptr, new_layout, old_layout, flags) }
+    }
+}
+
+// SAFETY: `KVmalloc` picks [`Kmalloc`] or [`Vmalloc`] by the same threshold as
+// `allocator::KVmalloc`; both are the host's `realloc`/`free` here, so a pointer produced by
+// either backend is valid to hand to the other.
+unsafe impl Allocator for KVmalloc {
+    unsafe fn realloc(
+        &self,
+        ptr: Option<NonNull<u8>>,
+        new_layout: Layout,
+        old_layout: Layout,
+        flags: Flags,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let ptr = ptr.filter(|_| old_layout.size() > 0);
+
+        // Whether the existing allocation (if any) was served by the "slab" path: this is what
+        // `ptr` was actually allocated through, and must drive which backend it is handed to
+        // below -- `new_layout.size()` alone says nothing about where `ptr` came from.
+        let old_in_slab = ptr.is_none() || old_layout.size() <= KVMALLOC_THRESHOLD;
+
+        if new_layout.size() <= KVMALLOC_THRESHOLD && old_in_slab {
+            // SAFETY: forwarding preserves `realloc`'s safety requirements; any existing
+            // allocation was itself served by the "slab" path.
+            return unsafe { Kmalloc.realloc(ptr, new_layout, old_layout, flags) };
+        }
+
+        // Either the new size is above the threshold or the existing allocation is on the
+        // "vmalloc" path. Unlike `allocator::KVmalloc`, crossing the threshold needs no copy: the
+        // host's `realloc` resizes a pointer from either backend.
+        // SAFETY: forwarding preserves `realloc`'s safety requirements.
+        unsafe { Vmalloc.realloc(ptr, new_layout, old_layout, flags) }
+    }
+}
+
+/// Mirrors `allocator::KernelAllocator`, the `#[global_allocator]` bridge, against this module's
+/// host-backed [`Kmalloc`] instead. Not registered as `#[global_allocator]` here, since the test
+/// harness itself needs the host's own allocator.
+#[cfg(test)]
+struct KernelAllocator;
+
+// SAFETY: see `allocator::KernelAllocator`'s impl; the same contract holds for this module's
+// `Kmalloc`.
+#[cfg(test)]
+unsafe impl core::alloc::GlobalAlloc for KernelAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: see `allocator::KernelAllocator::alloc`.
+        match unsafe { Kmalloc.allocate(layout, flags::GFP_KERNEL) } {
+            Ok(ptr) => ptr.as_ptr(),
+            Err(_) => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: see `allocator::KernelAllocator::dealloc`.
+        unsafe { Kmalloc.deallocate(NonNull::new_unchecked(ptr), layout) };
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: see `allocator::KernelAllocator::alloc_zeroed`.
+        match unsafe { Kmalloc.allocate(layout, flags::__GFP_ZERO) } {
+            Ok(ptr) => ptr.as_ptr(),
+            Err(_) => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let Ok(new_layout) = Layout::from_size_align(new_size, layout.align()) else {
+            return ptr::null_mut();
+        };
+
+        // SAFETY: see `allocator::KernelAllocator::realloc`.
+        match unsafe { Kmalloc.realloc(NonNull::new(ptr), new_layout, layout, flags::GFP_KERNEL) } {
+            Ok(ptr) => ptr.as_ptr(),
+            Err(_) => ptr::null_mut(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_flag_composes_with_a_context_flag() {
+        let combined = flags::__GFP_ZERO | flags::GFP_ATOMIC;
+
+        assert!(combined.contains(flags::__GFP_ZERO));
+        assert!(combined.contains(flags::GFP_ATOMIC));
+        assert!(!combined.contains(flags::__GFP_ACCOUNT));
+    }
+
+    #[test]
+    fn gfp_zero_clears_allocated_contents() {
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let ptr = Kmalloc
+            .allocate(layout, flags::__GFP_ZERO)
+            .expect("allocation failed");
+
+        // SAFETY: `ptr` was just allocated with `layout`.
+        let bytes = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), layout.size()) };
+        assert!(bytes.iter().all(|&b| b == 0));
+
+        // SAFETY: `ptr` was allocated by `Kmalloc` with `layout` and is not used afterwards.
+        unsafe { Kmalloc.deallocate(ptr, layout) };
+    }
+
+    #[test]
+    fn gfp_zero_clears_only_the_grown_tail_on_realloc() {
+        let old_layout = Layout::from_size_align(8, 8).unwrap();
+        let new_layout = Layout::from_size_align(64, 8).unwrap();
+
+        let ptr = Kmalloc
+            .allocate(old_layout, flags::GFP_KERNEL)
+            .expect("allocation failed");
+
+        // SAFETY: `ptr` is valid for writes of `old_layout.size()` bytes.
+        unsafe { ptr.as_ptr().write_bytes(0xaa, old_layout.size()) };
+
+        // SAFETY: `ptr` was allocated by `Kmalloc` with `old_layout`.
+        let ptr = unsafe {
+            Kmalloc
+                .realloc(Some(ptr), new_layout, old_layout, flags::__GFP_ZERO)
+                .expect("realloc failed")
+        };
+
+        // SAFETY: `ptr` is valid for reads of `new_layout.size()` bytes.
+        let bytes = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), new_layout.size()) };
+        assert!(bytes[..old_layout.size()].iter().all(|&b| b == 0xaa));
+        assert!(bytes[old_layout.size()..].iter().all(|&b| b == 0));
+
+        // SAFETY: `ptr` was allocated by `Kmalloc` with `new_layout` and is not used afterwards.
+        unsafe { Kmalloc.deallocate(ptr, new_layout) };
+    }
+
+    #[test]
+    fn gfp_zero_on_a_shrinking_realloc_does_not_offset_past_the_new_allocation() {
+        let old_layout = Layout::from_size_align(128, 8).unwrap();
+        let new_layout = Layout::from_size_align(32, 8).unwrap();
+
+        let ptr = Kmalloc
+            .allocate(old_layout, flags::GFP_KERNEL)
+            .expect("allocation failed");
+
+        // SAFETY: `ptr` is valid for writes of `old_layout.size()` bytes.
+        unsafe { ptr.as_ptr().write_bytes(0xaa, old_layout.size()) };
+
+        // SAFETY: `ptr` was allocated by `Kmalloc` with `old_layout`; `new_layout` is smaller, so
+        // there is no grown tail to zero and `old_size` must be clamped to `new_layout.size()`
+        // rather than offsetting past the shrunk allocation.
+        let ptr = unsafe {
+            Kmalloc
+                .realloc(Some(ptr), new_layout, old_layout, flags::__GFP_ZERO)
+                .expect("realloc failed")
+        };
+
+        // SAFETY: `ptr` is valid for reads of `new_layout.size()` bytes. Nothing grew, so the
+        // retained prefix is untouched rather than zeroed.
+        let bytes = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), new_layout.size()) };
+        assert!(bytes.iter().all(|&b| b == 0xaa));
+
+        // SAFETY: `ptr` was allocated by `Kmalloc` with `new_layout` and is not used afterwards.
+        unsafe { Kmalloc.deallocate(ptr, new_layout) };
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn get_stats_tracks_a_known_allocation_sequence() {
+        let before = Kmalloc.get_stats();
+
+        let layout = Layout::from_size_align(16, 8).unwrap();
+        let ptr = Kmalloc
+            .allocate(layout, flags::GFP_KERNEL)
+            .expect("allocation failed");
+
+        let grown_layout = Layout::from_size_align(128, 8).unwrap();
+        // SAFETY: `ptr` was allocated by `Kmalloc` with `layout`.
+        let ptr = unsafe {
+            Kmalloc
+                .realloc(Some(ptr), grown_layout, layout, flags::GFP_KERNEL)
+                .expect("realloc failed")
+        };
+
+        let during = Kmalloc.get_stats();
+        assert_eq!(during.current_usage - before.current_usage, 128);
+        assert!(during.peak_usage >= before.current_usage + 128);
+
+        // SAFETY: `ptr` was allocated by `Kmalloc` with `grown_layout` and is not used afterwards.
+        unsafe { Kmalloc.deallocate(ptr, grown_layout) };
+
+        let after = Kmalloc.get_stats();
+        assert_eq!(after.current_usage, before.current_usage);
+    }
+
+    #[test]
+    fn zero_size_allocation_never_touches_the_backing_allocator() {
+        for align in [1, 4096, 32] {
+            let layout = Layout::from_size_align(0, align).unwrap();
+            let ptr = Kmalloc
+                .allocate(layout, flags::GFP_KERNEL)
+                .expect("zero-size allocation must always succeed");
+
+            assert_eq!(ptr.as_ptr() as usize % align, 0);
+
+            // SAFETY: `ptr` was allocated by `Kmalloc` with `layout`; freeing a zero-size
+            // allocation is a no-op rather than a real call into `free`.
+            unsafe { Kmalloc.deallocate(ptr, layout) };
+        }
+    }
+
+    #[test]
+    fn growing_from_a_zero_size_allocation_allocates_fresh() {
+        let old_layout = Layout::from_size_align(0, 4096).unwrap();
+        let new_layout = Layout::from_size_align(64, 8).unwrap();
+
+        let ptr = Kmalloc
+            .allocate(old_layout, flags::GFP_KERNEL)
+            .expect("zero-size allocation must always succeed");
+
+        // SAFETY: `ptr` is the dangling pointer from the zero-size allocation above; `realloc`
+        // must recognize `old_layout.size() == 0` and not treat it as a real pointer to resize.
+        let ptr = unsafe {
+            Kmalloc
+                .realloc(Some(ptr), new_layout, old_layout, flags::GFP_KERNEL)
+                .expect("realloc failed")
+        };
+
+        // SAFETY: `ptr` is valid for writes of `new_layout.size()` bytes.
+        unsafe { ptr.as_ptr().write_bytes(0xaa, new_layout.size()) };
+
+        // SAFETY: `ptr` was allocated by `Kmalloc` with `new_layout` and is not used afterwards.
+        unsafe { Kmalloc.deallocate(ptr, new_layout) };
+    }
+
+    #[test]
+    fn global_alloc_bridge_round_trips_a_box_allocation() {
+        use core::alloc::GlobalAlloc;
+
+        let layout = Layout::new::<u64>();
+
+        // SAFETY: `layout` has a non-zero size.
+        let raw = unsafe { KernelAllocator.alloc(layout) };
+        assert!(!raw.is_null());
+
+        // SAFETY: `raw` was just allocated by `KernelAllocator` and is valid for writes of a
+        // `u64`, correctly aligned per `layout`.
+        unsafe { (raw as *mut u64).write(0xdead_beef) };
+
+        // SAFETY: `raw` is valid for reads of a `u64`, written above.
+        assert_eq!(unsafe { (raw as *const u64).read() }, 0xdead_beef);
+
+        // SAFETY: `raw` was allocated by `KernelAllocator` with `layout` and is not used
+        // afterwards.
+        unsafe { KernelAllocator.dealloc(raw, layout) };
+    }
+
+    #[test]
+    fn kvmalloc_growing_across_the_threshold_preserves_contents() {
+        let old_layout = Layout::from_size_align(KVMALLOC_THRESHOLD / 2, 8).unwrap();
+        let new_layout = Layout::from_size_align(KVMALLOC_THRESHOLD * 2, 8).unwrap();
+
+        let ptr = KVmalloc
+            .allocate(old_layout, flags::GFP_KERNEL)
+            .expect("allocation failed");
+
+        // SAFETY: `ptr` is valid for writes of `old_layout.size()` bytes.
+        unsafe { ptr.as_ptr().write_bytes(0xaa, old_layout.size()) };
+
+        // SAFETY: `ptr` was allocated by `KVmalloc` with `old_layout`; this grows the allocation
+        // past `KVMALLOC_THRESHOLD`, switching it from the "slab" to the "vmalloc" backend.
+        let ptr = unsafe {
+            KVmalloc
+                .realloc(Some(ptr), new_layout, old_layout, flags::GFP_KERNEL)
+                .expect("realloc failed")
+        };
+
+        // SAFETY: `ptr` is valid for reads of `new_layout.size()` bytes.
+        let bytes = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), new_layout.size()) };
+        assert!(bytes[..old_layout.size()].iter().all(|&b| b == 0xaa));
+
+        // SAFETY: `ptr` was allocated by `KVmalloc` with `new_layout` and is not used afterwards.
+        unsafe { KVmalloc.deallocate(ptr, new_layout) };
+    }
+
+    #[test]
+    fn kvmalloc_shrinking_across_the_threshold_preserves_contents() {
+        let old_layout = Layout::from_size_align(KVMALLOC_THRESHOLD * 2, 8).unwrap();
+        let new_layout = Layout::from_size_align(KVMALLOC_THRESHOLD / 2, 8).unwrap();
+
+        let ptr = KVmalloc
+            .allocate(old_layout, flags::GFP_KERNEL)
+            .expect("allocation failed");
+
+        // SAFETY: `ptr` is valid for writes of `new_layout.size()` bytes, which is less than
+        // `old_layout.size()`.
+        unsafe { ptr.as_ptr().write_bytes(0x55, new_layout.size()) };
+
+        // SAFETY: `ptr` was allocated by `KVmalloc` with `old_layout`, on the "vmalloc" backend
+        // since its size is above `KVMALLOC_THRESHOLD`; this shrinks it back below the
+        // threshold, which must not hand `ptr` to the "slab" backend instead.
+        let ptr = unsafe {
+            KVmalloc
+                .realloc(Some(ptr), new_layout, old_layout, flags::GFP_KERNEL)
+                .expect("realloc failed")
+        };
+
+        // SAFETY: `ptr` is valid for reads of `new_layout.size()` bytes.
+        let bytes = unsafe { core::slice::from_raw_parts(ptr.as_ptr(), new_layout.size()) };
+        assert!(bytes.iter().all(|&b| b == 0x55));
+
+        // SAFETY: `ptr` was allocated by `KVmalloc` with `new_layout` and is not used afterwards.
+        unsafe { KVmalloc.deallocate(ptr, new_layout) };
+    }
+}
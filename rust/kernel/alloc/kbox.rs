@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: GPL-2.0
+
+//! Implementation of [`Box`] for the kernel.
+
+use super::{allocator::Kmalloc, AllocError, Allocator, Flags};
+use core::{alloc::Layout, marker::PhantomData, ops::Deref, ops::DerefMut, ptr::NonNull};
+
+/// The kernel's [`Box`] type -- a heap allocation for a single value of `T`, generic over the
+/// backing [`Allocator`] `A`.
+///
+/// Use [`KBox`], [`VBox`] or [`KVBox`] instead of naming this type directly, unless writing code
+/// that is generic over the allocator.
+pub struct Box<T: ?Sized, A: Allocator> {
+    ptr: NonNull<T>,
+    _p: PhantomData<A>,
+}
+
+/// A [`Box`] backed by the kernel's slab allocator ([`Kmalloc`]).
+pub type KBox<T> = Box<T, Kmalloc>;
+
+/// A [`Box`] backed by the kernel's virtually contiguous allocator ([`super::allocator::Vmalloc`]).
+pub type VBox<T> = Box<T, super::allocator::Vmalloc>;
+
+/// A [`Box`] backed by the kernel's [`super::allocator::KVmalloc`] allocator.
+pub type KVBox<T> = Box<T, super::allocator::KVmalloc>;
+
+// SAFETY: `Box` does not add any restriction on `T` and can be moved to a different thread iff
+// `T` can.
+unsafe impl<T: ?Sized + Send, A: Allocator> Send for Box<T, A> {}
+
+// SAFETY: `Box` does not add any restriction on `T` and can be shared with a different thread iff
+// `T` can.
+unsafe impl<T: ?Sized + Sync, A: Allocator> Sync for Box<T, A> {}
+
+impl<T, A: Allocator + Default> Box<T, A> {
+    /// Allocates a new [`Box`] and moves `x` into it.
+    pub fn new(x: T, flags: Flags) -> Result<Self, AllocError> {
+        let layout = Layout::new::<T>();
+        let alloc = A::default();
+        let ptr = alloc.allocate(layout, flags)?.cast::<T>();
+
+        // SAFETY: `ptr` was just allocated with `layout` and is valid for writes of `T`.
+        unsafe { ptr.as_ptr().write(x) };
+
+        Ok(Self {
+            ptr,
+            _p: PhantomData,
+        })
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Deref for Box<T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: `self.ptr` is always valid for reads for the lifetime of `self`.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> DerefMut for Box<T, A> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: `self.ptr` is always valid for reads and writes for the lifetime of `self`.
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T: ?Sized, A: Allocator + Default> Drop for Box<T, A> {
+    fn drop(&mut self) {
+        let layout = Layout::for_value::<T>(self);
+
+        // SAFETY: `self.ptr` is valid by the type invariant and about to be dropped, so no one
+        // can observe it afterwards.
+        unsafe { core::ptr::drop_in_place(self.ptr.as_ptr()) };
+
+        // SAFETY: `self.ptr` was allocated by `A` with `layout` and is not used afterwards.
+        unsafe { A::default().deallocate(self.ptr.cast(), layout) };
+    }
+}
@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: GPL-2.0
+
+//! Implementation of [`Vec`] for the kernel.
+
+use super::{
+    allocator::{KVmalloc, Kmalloc, Vmalloc},
+    layout::ArrayLayout,
+    AllocError, Allocator, Flags,
+};
+use core::{marker::PhantomData, ops::Deref, ops::DerefMut, ptr::NonNull};
+
+/// The kernel's `Vec` type -- a growable array of `T`, generic over the backing [`Allocator`]
+/// `A`.
+///
+/// Use [`KVec`], [`VVec`] or [`KVVec`] instead of naming this type directly, unless writing code
+/// that is generic over the allocator.
+pub struct Vec<T, A: Allocator> {
+    ptr: NonNull<T>,
+    len: usize,
+    layout: ArrayLayout<T>,
+    _p: PhantomData<A>,
+}
+
+/// A [`Vec`] backed by the kernel's slab allocator ([`Kmalloc`]).
+pub type KVec<T> = Vec<T, Kmalloc>;
+
+/// A [`Vec`] backed by the kernel's virtually contiguous allocator ([`Vmalloc`]).
+pub type VVec<T> = Vec<T, Vmalloc>;
+
+/// A [`Vec`] backed by the kernel's [`KVmalloc`] allocator.
+pub type KVVec<T> = Vec<T, KVmalloc>;
+
+// SAFETY: `Vec` does not add any restriction on `T` and can be moved to a different thread iff
+// `T` can.
+unsafe impl<T: Send, A: Allocator> Send for Vec<T, A> {}
+
+// SAFETY: `Vec` does not add any restriction on `T` and can be shared with a different thread iff
+// `T` can.
+unsafe impl<T: Sync, A: Allocator> Sync for Vec<T, A> {}
+
+impl<T, A: Allocator + Default> Vec<T, A> {
+    /// Creates a new, empty `Vec`. Does not allocate until elements are pushed onto it.
+    pub const fn new() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            len: 0,
+            // SAFETY: `0 * size_of::<T>()` never overflows.
+            layout: unsafe { ArrayLayout::new_unchecked(0) },
+            _p: PhantomData,
+        }
+    }
+
+    /// Appends an element to the back of the `Vec`, growing the backing allocation through `A`
+    /// if there is no spare capacity.
+    pub fn push(&mut self, v: T, flags: Flags) -> Result<(), AllocError> {
+        if self.len == self.layout.len() {
+            self.grow(flags)?;
+        }
+
+        // SAFETY: `self.len < self.layout.len()` after the potential `grow` above, so `self.ptr`
+        // has room for at least one more element at offset `self.len`.
+        unsafe { self.ptr.as_ptr().add(self.len).write(v) };
+        self.len += 1;
+
+        Ok(())
+    }
+
+    fn grow(&mut self, flags: Flags) -> Result<(), AllocError> {
+        let new_len = core::cmp::max(self.layout.len() * 2, 4);
+        let new_layout = ArrayLayout::<T>::new(new_len).ok_or(AllocError::InvalidAlignment)?;
+
+        let alloc = A::default();
+        let old_ptr = (self.layout.len() > 0).then_some(self.ptr.cast());
+
+        // SAFETY: `old_ptr` is `None` if and only if `self.layout` is the zero-sized layout;
+        // otherwise it was allocated by `alloc` with `self.layout.into_layout()`.
+        let new_ptr = unsafe {
+            alloc.realloc(
+                old_ptr,
+                new_layout.into_layout(),
+                self.layout.into_layout(),
+                flags,
+            )?
+        };
+
+        self.ptr = new_ptr.cast();
+        self.layout = new_layout;
+
+        Ok(())
+    }
+}
+
+impl<T, A: Allocator> Deref for Vec<T, A> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        // SAFETY: `self.ptr` points to `self.len` initialized elements of `T`.
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T, A: Allocator> DerefMut for Vec<T, A> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        // SAFETY: `self.ptr` points to `self.len` initialized elements of `T`.
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T, A: Allocator + Default> Drop for Vec<T, A> {
+    fn drop(&mut self) {
+        // SAFETY: the first `self.len` elements of `self.ptr` are initialized.
+        unsafe { core::ptr::drop_in_place(self.deref_mut() as *mut [T]) };
+
+        if self.layout.len() > 0 {
+            // SAFETY: `self.ptr` was allocated by `A` with `self.layout.into_layout()` and is not
+            // used afterwards.
+            unsafe { A::default().deallocate(self.ptr.cast(), self.layout.into_layout()) };
+        }
+    }
+}
+
+/// An iterator that moves out of a [`Vec`].
+pub struct IntoIter<T, A: Allocator> {
+    vec: Vec<T, A>,
+    idx: usize,
+}
+
+impl<T, A: Allocator + Default> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.idx == self.vec.len {
+            return None;
+        }
+
+        // SAFETY: `self.idx < self.vec.len`, so this element is initialized and has not been
+        // moved out of yet.
+        let v = unsafe { self.vec.ptr.as_ptr().add(self.idx).read() };
+        self.idx += 1;
+
+        Some(v)
+    }
+}
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: GPL-2.0
+
+//! Layout helpers for computing allocation sizes without overflow.
+
+use core::{alloc::Layout, marker::PhantomData};
+
+/// A layout for an array of `T`, with the element count tracked separately from the raw
+/// [`Layout`] so that [`KVec`](super::kvec::KVec) and friends can grow or shrink without
+/// recomputing the layout from scratch on every call.
+///
+/// Constructing an [`ArrayLayout`] never overflows; [`ArrayLayout::new`] returns `None` instead.
+pub(crate) struct ArrayLayout<T> {
+    len: usize,
+    _p: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for ArrayLayout<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ArrayLayout<T> {}
+
+impl<T> ArrayLayout<T> {
+    /// Creates a new [`ArrayLayout`] for `len` elements of `T`.
+    ///
+    /// Returns `None` if the resulting size would overflow `isize::MAX`.
+    pub(crate) const fn new(len: usize) -> Option<Self> {
+        if let Some(size) = core::mem::size_of::<T>().checked_mul(len) {
+            if size <= isize::MAX as usize {
+                return Some(Self {
+                    len,
+                    _p: PhantomData,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Creates a new [`ArrayLayout`] for `len` elements of `T` without checking for overflow.
+    ///
+    /// # Safety
+    ///
+    /// `len * size_of::<T>()` must not overflow `isize::MAX`.
+    pub(crate) const unsafe fn new_unchecked(len: usize) -> Self {
+        Self {
+            len,
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements this layout was created for.
+    pub(crate) const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Converts this [`ArrayLayout`] into a [`Layout`] for `len` elements of `T`.
+    pub(crate) const fn into_layout(self) -> Layout {
+        // SAFETY: Invariant of `Self` guarantees that the resulting size does not overflow
+        // `isize::MAX`.
+        unsafe { Layout::from_size_align_unchecked(self.len * core::mem::size_of::<T>(), core::mem::align_of::<T>()) }
+    }
+}